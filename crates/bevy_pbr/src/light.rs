@@ -1,11 +1,54 @@
 use bevy_core::Byteable;
-use bevy_ecs::reflect::ReflectComponent;
-use bevy_math::Vec3;
+use bevy_ecs::{bundle::Bundle, reflect::ReflectComponent};
+use bevy_math::{Mat4, Vec3};
 use bevy_reflect::Reflect;
 use bevy_render::color::Color;
-use bevy_transform::components::GlobalTransform;
+use bevy_transform::components::{GlobalTransform, Transform};
 
-/// A point light
+/// The physical settings of a camera, used to convert light values from
+/// photometric units (lux, lumens) into the exposure-scaled values the
+/// shaders expect.
+///
+/// See: <https://google.github.io/filament/Filament.html#imagingpipeline/physicallybasedcamera/exposuresettings>
+#[derive(Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Exposure {
+    /// Aperture, in f-stops.
+    pub aperture: f32,
+    /// Shutter speed, in seconds.
+    pub shutter_speed: f32,
+    /// Sensitivity, in ISO.
+    pub sensitivity: f32,
+}
+
+impl Exposure {
+    /// The exposure value for 100 ISO (EV100), derived from the aperture,
+    /// shutter speed and sensitivity.
+    pub fn ev100(&self) -> f32 {
+        f32::log2(self.aperture * self.aperture / self.shutter_speed)
+            - f32::log2(self.sensitivity / 100.0)
+    }
+
+    /// The exposure factor to multiply light intensities by before they
+    /// reach the GPU.
+    pub fn exposure(&self) -> f32 {
+        1.0 / (f32::powf(2.0, self.ev100()) * 1.2)
+    }
+}
+
+impl Default for Exposure {
+    fn default() -> Self {
+        Exposure {
+            aperture: 4.0,
+            shutter_speed: 1.0 / 250.0,
+            sensitivity: 100.0,
+        }
+    }
+}
+
+/// A point light.
+///
+/// `intensity` is the luminous power of the light, in lumens.
 #[derive(Debug, Clone, Copy, Reflect)]
 #[reflect(Component)]
 pub struct PointLight {
@@ -13,6 +56,14 @@ pub struct PointLight {
     pub intensity: f32,
     pub range: f32,
     pub radius: f32,
+    /// Whether this light casts shadows, via a shadow cube map.
+    pub shadows_enabled: bool,
+    /// Depth bias applied along the light-to-fragment direction to fight
+    /// shadow acne, in the same units as `range`.
+    pub shadow_depth_bias: f32,
+    /// Bias applied along the surface normal to fight shadow acne, scaled
+    /// by the texel size of the shadow map.
+    pub shadow_normal_bias: f32,
 }
 
 impl Default for PointLight {
@@ -22,10 +73,51 @@ impl Default for PointLight {
             intensity: 200.0,
             range: 20.0,
             radius: 0.0,
+            shadows_enabled: false,
+            shadow_depth_bias: Self::DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_normal_bias: Self::DEFAULT_SHADOW_NORMAL_BIAS,
         }
     }
 }
 
+impl PointLight {
+    pub const DEFAULT_SHADOW_DEPTH_BIAS: f32 = 0.02;
+    pub const DEFAULT_SHADOW_NORMAL_BIAS: f32 = 0.6;
+}
+
+// NOTE: `shadows_enabled`, the bias fields, and the `shadow_view_proj`
+// matrices computed below are CPU-side config and math only. There is no
+// depth-pass render-graph node, shadow texture/cube map resource, or PCF
+// sampling in the PBR shader yet, so enabling shadows currently has no
+// visual effect; wiring up that rendering half is tracked as follow-up
+// work.
+
+/// Near plane distance used when rendering a point light's shadow cube map.
+const POINT_LIGHT_SHADOW_NEAR: f32 = 0.1;
+
+/// View direction and up vector of each face of a shadow cube map, in the
+/// conventional `+X, -X, +Y, -Y, +Z, -Z` cube map face order.
+const CUBE_MAP_FACES: [(Vec3, Vec3); 6] = [
+    (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0)),
+    (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0)),
+    (Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+    (Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+    (Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, -1.0, 0.0)),
+    (Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, -1.0, 0.0)),
+];
+
+/// An up vector to build a shadow view matrix from, given a light
+/// direction. Falls back to the Z axis when the direction is near-vertical,
+/// since `Y` and `direction` would otherwise be near-parallel and produce a
+/// degenerate `look_at`.
+fn shadow_view_up(direction: Vec3) -> Vec3 {
+    if direction.dot(Vec3::new(0.0, 1.0, 0.0)).abs() > 0.99 {
+        Vec3::new(0.0, 0.0, 1.0)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0)
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct PointLightUniform {
@@ -33,22 +125,48 @@ pub(crate) struct PointLightUniform {
     pub color: [f32; 4],
     // storing as a `[f32; 4]` for memory alignement
     pub light_params: [f32; 4],
+    /// View-projection matrix of each of the 6 shadow cube map faces, for
+    /// the PBR pass to sample against when filtering shadows.
+    pub shadow_view_proj: [[[f32; 4]; 4]; 6],
 }
 
 unsafe impl Byteable for PointLightUniform {}
 
 impl PointLightUniform {
-    pub fn new(light: &PointLight, global_transform: &GlobalTransform) -> PointLightUniform {
-        let (x, y, z) = global_transform.translation.into();
+    pub fn new(
+        light: &PointLight,
+        global_transform: &GlobalTransform,
+        exposure: f32,
+    ) -> PointLightUniform {
+        let position = global_transform.translation;
+        let (x, y, z) = position.into();
 
-        // premultiply color by intensity
+        // convert from luminous power (lumens) to luminous intensity (candelas)
+        let intensity_cd = light.intensity / (4.0 * std::f32::consts::PI);
+
+        // premultiply color by intensity and exposure
         // we don't use the alpha at all, so no reason to multiply only [0..3]
-        let color: [f32; 4] = (light.color * light.intensity).into();
+        let color: [f32; 4] = (light.color * intensity_cd * exposure).into();
+
+        // one 90 degree perspective projection per cube map face, looking
+        // out from the light's position
+        let proj = Mat4::perspective_rh(
+            std::f32::consts::FRAC_PI_2,
+            1.0,
+            POINT_LIGHT_SHADOW_NEAR,
+            light.range,
+        );
+        let mut shadow_view_proj = [[[0.0; 4]; 4]; 6];
+        for (i, (dir, up)) in CUBE_MAP_FACES.iter().enumerate() {
+            let view = Mat4::look_at_rh(position, position + *dir, *up);
+            shadow_view_proj[i] = (proj * view).to_cols_array_2d();
+        }
 
         PointLightUniform {
             pos: [x, y, z, 1.0],
             color,
             light_params: [1.0 / (light.range * light.range), light.radius, 0.0, 0.0],
+            shadow_view_proj,
         }
     }
 }
@@ -66,6 +184,22 @@ pub struct DirectionalLight {
     pub color: Color,
     pub intensity: f32,
     direction: Vec3,
+    /// Whether this light casts shadows, via a single orthographic depth map.
+    pub shadows_enabled: bool,
+    /// Depth bias applied along the light direction to fight shadow acne,
+    /// in world units.
+    pub shadow_depth_bias: f32,
+    /// Bias applied along the surface normal to fight shadow acne, scaled
+    /// by the texel size of the shadow map.
+    pub shadow_normal_bias: f32,
+    /// Half-size, in world units, of the square orthographic volume the
+    /// shadow map is rendered from. Should cover the area of the scene
+    /// that needs to cast and receive shadows.
+    pub shadow_projection_half_size: f32,
+    /// Near plane distance of the orthographic shadow volume.
+    pub shadow_near: f32,
+    /// Far plane distance of the orthographic shadow volume.
+    pub shadow_far: f32,
 }
 
 impl DirectionalLight {
@@ -82,6 +216,12 @@ impl DirectionalLight {
             color,
             intensity,
             direction,
+            shadows_enabled: false,
+            shadow_depth_bias: Self::DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_normal_bias: Self::DEFAULT_SHADOW_NORMAL_BIAS,
+            shadow_projection_half_size: Self::DEFAULT_SHADOW_PROJECTION_HALF_SIZE,
+            shadow_near: Self::DEFAULT_SHADOW_NEAR,
+            shadow_far: Self::DEFAULT_SHADOW_FAR,
         }
     }
 
@@ -100,6 +240,12 @@ impl DirectionalLight {
     pub fn get_direction(&self) -> Vec3 {
         self.direction
     }
+
+    pub const DEFAULT_SHADOW_DEPTH_BIAS: f32 = 0.02;
+    pub const DEFAULT_SHADOW_NORMAL_BIAS: f32 = 0.6;
+    pub const DEFAULT_SHADOW_PROJECTION_HALF_SIZE: f32 = 10.0;
+    pub const DEFAULT_SHADOW_NEAR: f32 = 0.1;
+    pub const DEFAULT_SHADOW_FAR: f32 = 100.0;
 }
 
 impl Default for DirectionalLight {
@@ -108,6 +254,12 @@ impl Default for DirectionalLight {
             color: Color::rgb(1.0, 1.0, 1.0),
             intensity: 100000.0,
             direction: Vec3::new(0.0, -1.0, 0.0),
+            shadows_enabled: false,
+            shadow_depth_bias: Self::DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_normal_bias: Self::DEFAULT_SHADOW_NORMAL_BIAS,
+            shadow_projection_half_size: Self::DEFAULT_SHADOW_PROJECTION_HALF_SIZE,
+            shadow_near: Self::DEFAULT_SHADOW_NEAR,
+            shadow_far: Self::DEFAULT_SHADOW_FAR,
         }
     }
 }
@@ -117,12 +269,19 @@ impl Default for DirectionalLight {
 pub(crate) struct DirectionalLightUniform {
     pub dir: [f32; 4],
     pub color: [f32; 4],
+    /// View-projection matrix of the light's orthographic shadow volume,
+    /// for the PBR pass to sample the shadow map against.
+    pub shadow_view_proj: [[f32; 4]; 4],
 }
 
 unsafe impl Byteable for DirectionalLightUniform {}
 
 impl DirectionalLightUniform {
-    pub fn new(light: &DirectionalLight) -> DirectionalLightUniform {
+    pub fn new(
+        light: &DirectionalLight,
+        global_transform: &GlobalTransform,
+        exposure: f32,
+    ) -> DirectionalLightUniform {
         // direction is negated to be ready for N.L
         let dir: [f32; 4] = [
             -light.direction.x,
@@ -131,23 +290,277 @@ impl DirectionalLightUniform {
             0.0,
         ];
 
-        // convert from illuminance (lux) to candelas
-        //
-        // exposure is hard coded at the moment but should be replaced
-        // by values coming from the camera
-        // see: https://google.github.io/filament/Filament.html#imagingpipeline/physicallybasedcamera/exposuresettings
-        const APERTURE: f32 = 4.0;
-        const SHUTTER_SPEED: f32 = 1.0 / 250.0;
-        const SENSITIVITY: f32 = 100.0;
-        let ev100 = f32::log2(APERTURE * APERTURE / SHUTTER_SPEED) - f32::log2(SENSITIVITY / 100.0);
-        let exposure = 1.0 / (f32::powf(2.0, ev100) * 1.2);
+        // convert from illuminance (lux) to candelas, scaled by the
+        // active camera's exposure
         let intensity = light.intensity * exposure;
 
         // premultiply color by intensity
         // we don't use the alpha at all, so no reason to multiply only [0..3]
         let color: [f32; 4] = (light.color * intensity).into();
 
-        DirectionalLightUniform { dir, color }
+        // the shadow volume is centered on the light's transform and faces
+        // along its direction
+        let position = global_transform.translation;
+        let up = shadow_view_up(light.direction);
+        let view = Mat4::look_at_rh(position, position + light.direction, up);
+        let half = light.shadow_projection_half_size;
+        let proj = Mat4::orthographic_rh(
+            -half,
+            half,
+            -half,
+            half,
+            light.shadow_near,
+            light.shadow_far,
+        );
+
+        DirectionalLightUniform {
+            dir,
+            color,
+            shadow_view_proj: (proj * view).to_cols_array_2d(),
+        }
+    }
+}
+
+/// A spot light.
+///
+/// Spot lights emit light in a cone shape, with the intensity falling off
+/// smoothly between the `inner_angle` and the `outer_angle`.
+///
+/// Like [`PointLight`], `intensity` is the luminous power of the light, in
+/// lumens.
+#[derive(Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct SpotLight {
+    pub color: Color,
+    pub intensity: f32,
+    pub range: f32,
+    pub radius: f32,
+    direction: Vec3,
+    /// Half-angle, in radians, at which the light is at full intensity.
+    pub inner_angle: f32,
+    /// Half-angle, in radians, at which the light intensity reaches zero.
+    pub outer_angle: f32,
+    /// Whether this light casts shadows, via a single perspective depth map.
+    pub shadows_enabled: bool,
+    /// Depth bias applied along the light-to-fragment direction to fight
+    /// shadow acne, in the same units as `range`.
+    pub shadow_depth_bias: f32,
+    /// Bias applied along the surface normal to fight shadow acne, scaled
+    /// by the texel size of the shadow map.
+    pub shadow_normal_bias: f32,
+}
+
+impl SpotLight {
+    /// Create a new spot light component.
+    ///
+    /// # Panics
+    /// Will panic if `direction` is not normalized.
+    pub fn new(
+        color: Color,
+        intensity: f32,
+        range: f32,
+        radius: f32,
+        direction: Vec3,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Self {
+        assert!(
+            direction.is_normalized(),
+            "Light direction vector should have been normalized."
+        );
+        SpotLight {
+            color,
+            intensity,
+            range,
+            radius,
+            direction,
+            inner_angle,
+            outer_angle,
+            shadows_enabled: false,
+            shadow_depth_bias: Self::DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_normal_bias: Self::DEFAULT_SHADOW_NORMAL_BIAS,
+        }
+    }
+
+    /// Set direction of light.
+    ///
+    /// # Panics
+    /// Will panic if `direction` is not normalized.
+    pub fn set_direction(&mut self, direction: Vec3) {
+        assert!(
+            direction.is_normalized(),
+            "Light direction vector should have been normalized."
+        );
+        self.direction = direction;
+    }
+
+    pub fn get_direction(&self) -> Vec3 {
+        self.direction
+    }
+
+    pub const DEFAULT_SHADOW_DEPTH_BIAS: f32 = 0.02;
+    pub const DEFAULT_SHADOW_NORMAL_BIAS: f32 = 0.6;
+}
+
+impl Default for SpotLight {
+    fn default() -> Self {
+        SpotLight {
+            color: Color::rgb(1.0, 1.0, 1.0),
+            intensity: 200.0,
+            range: 20.0,
+            radius: 0.0,
+            direction: Vec3::new(0.0, -1.0, 0.0),
+            inner_angle: 0.3,
+            outer_angle: 0.6,
+            shadows_enabled: false,
+            shadow_depth_bias: Self::DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_normal_bias: Self::DEFAULT_SHADOW_NORMAL_BIAS,
+        }
+    }
+}
+
+/// Near plane distance used when rendering a spot light's shadow map.
+const SPOT_LIGHT_SHADOW_NEAR: f32 = 0.1;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SpotLightUniform {
+    pub pos: [f32; 4],
+    pub dir: [f32; 4],
+    pub color: [f32; 4],
+    // storing as a `[f32; 4]` for memory alignement
+    pub light_params: [f32; 4],
+    /// View-projection matrix of the light's perspective shadow volume, for
+    /// the PBR pass to sample the shadow map against.
+    pub shadow_view_proj: [[f32; 4]; 4],
+}
+
+unsafe impl Byteable for SpotLightUniform {}
+
+impl SpotLightUniform {
+    pub fn new(
+        light: &SpotLight,
+        global_transform: &GlobalTransform,
+        exposure: f32,
+    ) -> SpotLightUniform {
+        let position = global_transform.translation;
+        let (x, y, z) = position.into();
+
+        // direction is negated, so dot(L, dir) gives the cosine between the
+        // light-to-fragment vector (-L) and the spot direction
+        let dir: [f32; 4] = [
+            -light.direction.x,
+            -light.direction.y,
+            -light.direction.z,
+            0.0,
+        ];
+
+        // convert from luminous power (lumens) to luminous intensity (candelas)
+        let intensity_cd = light.intensity / (4.0 * std::f32::consts::PI);
+
+        // premultiply color by intensity and exposure
+        // we don't use the alpha at all, so no reason to multiply only [0..3]
+        let color: [f32; 4] = (light.color * intensity_cd * exposure).into();
+
+        // perspective shadow volume covering the full outer cone
+        let up = shadow_view_up(light.direction);
+        let view = Mat4::look_at_rh(position, position + light.direction, up);
+        let proj = Mat4::perspective_rh(
+            light.outer_angle * 2.0,
+            1.0,
+            SPOT_LIGHT_SHADOW_NEAR,
+            light.range,
+        );
+
+        SpotLightUniform {
+            pos: [x, y, z, 1.0],
+            dir,
+            color,
+            light_params: [
+                1.0 / (light.range * light.range),
+                light.radius,
+                light.inner_angle.cos(),
+                light.outer_angle.cos(),
+            ],
+            shadow_view_proj: (proj * view).to_cols_array_2d(),
+        }
+    }
+}
+
+/// A light source.
+///
+/// Wraps each concrete light component so a single heterogeneous query or
+/// collection can gather every light source in the scene for uniform
+/// upload, instead of querying each light type separately.
+///
+/// Doesn't derive `Reflect` yet: the vendored `bevy_reflect` derive macro
+/// only handles structs, not enums.
+#[derive(Debug, Clone, Copy)]
+pub enum Light {
+    Point(PointLight),
+    Directional(DirectionalLight),
+    Spot(SpotLight),
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Light::Point(light)
+    }
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(light: DirectionalLight) -> Self {
+        Light::Directional(light)
+    }
+}
+
+impl From<SpotLight> for Light {
+    fn from(light: SpotLight) -> Self {
+        Light::Spot(light)
+    }
+}
+
+/// The GPU uniform produced by [`Light::uniform`], tagged by light kind so
+/// the render graph can upload it to the matching light array.
+pub(crate) enum LightUniform {
+    Point(PointLightUniform),
+    Directional(DirectionalLightUniform),
+    Spot(SpotLightUniform),
+}
+
+impl Light {
+    /// Build the GPU uniform for this light, dispatching to the concrete
+    /// light type's uniform constructor.
+    pub(crate) fn uniform(&self, transform: &GlobalTransform, exposure: f32) -> LightUniform {
+        match self {
+            Light::Point(light) => {
+                LightUniform::Point(PointLightUniform::new(light, transform, exposure))
+            }
+            Light::Directional(light) => {
+                LightUniform::Directional(DirectionalLightUniform::new(light, transform, exposure))
+            }
+            Light::Spot(light) => {
+                LightUniform::Spot(SpotLightUniform::new(light, transform, exposure))
+            }
+        }
+    }
+}
+
+/// A component bundle for spawning any kind of light source.
+#[derive(Bundle, Debug)]
+pub struct LightBundle {
+    pub light: Light,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+}
+
+impl Default for LightBundle {
+    fn default() -> Self {
+        LightBundle {
+            light: Light::Point(PointLight::default()),
+            transform: Default::default(),
+            global_transform: Default::default(),
+        }
     }
 }
 
@@ -167,3 +580,11 @@ impl Default for AmbientLight {
         }
     }
 }
+
+impl AmbientLight {
+    /// The ambient color, premultiplied by brightness and the active
+    /// camera's exposure.
+    pub fn color(&self, exposure: f32) -> Color {
+        self.color * self.brightness * exposure
+    }
+}